@@ -8,13 +8,19 @@ use crate::{
 use futures::{Stream, StreamExt};
 use reth_beacon_consensus::{BeaconConsensusEngineEvent, BeaconEngineMessage};
 use reth_engine_primitives::EngineTypes;
-use reth_primitives::{SealedBlockWithSenders, B256};
+use reth_primitives::{Bytes, BlobTransactionSidecar, SealedBlockWithSenders, B256};
+use reth_rpc_types::engine::ExecutionPayloadBodyV1;
 use std::{
-    collections::HashSet,
-    sync::mpsc::Sender,
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{mpsc::Sender, Arc},
     task::{ready, Context, Poll},
 };
-use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::{mpsc::UnboundedReceiver, oneshot, watch};
+use tokio_stream::wrappers::WatchStream;
+
+/// The default cap on how many requests the [`EngineHandler`]'s scheduler buffers per
+/// [`RequestPriority`] class before dropping the oldest entry to make room for the newest.
+const DEFAULT_MAX_QUEUE_LEN: usize = 256;
 
 /// A [`ChainHandler`] that advances the chain based on incoming requests (CL engine API).
 ///
@@ -30,11 +36,21 @@ use tokio::sync::mpsc::UnboundedReceiver;
 /// - Delegating incoming requests to the [`EngineRequestHandler`].
 /// - Advancing the [`EngineRequestHandler`] by polling it and emitting events.
 /// - Downloading blocks on demand from the network if requested by the [`EngineApiRequestHandler`].
+/// - Pausing incoming requests and downloads while the tree executor reports itself as
+///   [`EngineState::Offline`] or [`EngineState::Syncing`], resuming once it's back online.
+/// - Scheduling incoming requests through a [`RequestScheduler`] so latency-critical
+///   `forkchoiceUpdated` requests are never starved by a burst of `newPayload` requests.
+/// - Bootstrapping the tree from a [`CheckpointSyncConfig`] anchor, if one is configured, and
+///   deferring live `BlockRange` downloads below it until [`BackfillAction::Backfill`] can take
+///   over.
 ///
 /// The core logic is part of the [`EngineRequestHandler`], which is responsible for processing the
 /// incoming requests.
 #[derive(Debug)]
-pub struct EngineHandler<T, S, D> {
+pub struct EngineHandler<T, S, D>
+where
+    T: EngineRequestHandler,
+{
     /// Processes requests.
     ///
     /// This type is responsible for processing incoming requests.
@@ -43,22 +59,87 @@ pub struct EngineHandler<T, S, D> {
     incoming_requests: S,
     /// A downloader to download blocks on demand.
     downloader: D,
+    /// Blocks that were downloaded but are waiting on their blob sidecars before they can be
+    /// forwarded to the handler.
+    pending_blobs: HashMap<B256, PendingBlobBlock>,
+    /// Notifies when the tree executor's [`EngineState`] changes, so downloading and request
+    /// processing can be paused while the tree can't consume the results.
+    state: WatchStream<EngineState>,
+    /// The last observed [`EngineState`], used to detect transitions.
+    last_state: EngineState,
+    /// Download requests that are currently outstanding, tracked so they can be re-requested if
+    /// the tree goes offline and the downloader is cleared. Entries are removed as their blocks
+    /// or blobs arrive, rather than only on a full clear.
+    in_flight_downloads: Vec<InFlightDownload>,
+    /// Download requests that were cleared, or requested, while the tree was offline, so they can
+    /// be requested once it comes back online.
+    paused_downloads: Vec<InFlightDownload>,
+    /// Bounded, prioritized queues of requests waiting to be handed to the handler.
+    scheduler: RequestScheduler<T::Request>,
+    /// Pending fetch of the checkpoint sync anchor block, if configured. Cleared once it
+    /// resolves.
+    checkpoint: Option<Box<dyn CheckpointFetcher>>,
+    /// `false` until the configured checkpoint fetch resolves, whether the anchor was fetched or
+    /// the fetch failed (`true` from the start if no checkpoint was configured). While `false`,
+    /// live `BlockRange` downloads are deferred since the gap below the anchor is backfill's job,
+    /// not live sync's.
+    bootstrapped: bool,
+    /// `BlockRange` downloads deferred while waiting on the checkpoint anchor.
+    deferred_block_ranges: Vec<DownloadRequest>,
+    /// `BlockRange` downloads waiting for the currently in-flight one to finish.
+    ///
+    /// At most one `BlockRange` is ever in flight at a time: a completed block can only be
+    /// attributed to *some* in-flight range, not a specific one (`DownloadRequest::BlockRange`
+    /// only carries a start hash and count, not the set of hashes it covers), so keeping more than
+    /// one in flight would let blocks from one range incorrectly complete another.
+    queued_block_ranges: VecDeque<DownloadRequest>,
 }
 
-impl<T, S, D> EngineHandler<T, S, D> {
+impl<T, S, D> EngineHandler<T, S, D>
+where
+    T: EngineRequestHandler,
+    T::Request: PrioritizedRequest,
+{
     /// Creates a new [`EngineHandler`] with the given handler and downloader and incoming stream of
     /// requests.
-    pub const fn new(handler: T, downloader: D, incoming_requests: S) -> Self
-    where
-        T: EngineRequestHandler,
-    {
-        Self { handler, incoming_requests, downloader }
+    pub fn new(handler: T, downloader: D, incoming_requests: S) -> Self {
+        let state = WatchStream::new(handler.state_changes());
+        Self {
+            handler,
+            incoming_requests,
+            downloader,
+            pending_blobs: HashMap::new(),
+            state,
+            last_state: EngineState::Online,
+            in_flight_downloads: Vec::new(),
+            paused_downloads: Vec::new(),
+            scheduler: RequestScheduler::new(DEFAULT_MAX_QUEUE_LEN),
+            checkpoint: None,
+            bootstrapped: true,
+            deferred_block_ranges: Vec::new(),
+            queued_block_ranges: VecDeque::new(),
+        }
+    }
+
+    /// Sets the maximum number of requests buffered per [`RequestPriority`] class.
+    pub const fn with_max_queue_len(mut self, max_queue_len: usize) -> Self {
+        self.scheduler.max_queue_len = max_queue_len;
+        self
+    }
+
+    /// Configures a checkpoint sync anchor to bootstrap the tree with, fetched lazily via
+    /// `fetcher` the first time this handler is polled.
+    pub fn with_checkpoint(mut self, fetcher: impl CheckpointFetcher) -> Self {
+        self.checkpoint = Some(Box::new(fetcher));
+        self.bootstrapped = false;
+        self
     }
 }
 
 impl<T, S, D> ChainHandler for EngineHandler<T, S, D>
 where
     T: EngineRequestHandler,
+    T::Request: PrioritizedRequest,
     S: Stream<Item = T::Request> + Send + Sync + Unpin + 'static,
     D: BlockDownloader,
 {
@@ -71,6 +152,44 @@ where
 
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<HandlerEvent<Self::Event>> {
         loop {
+            // pick up the latest state transition from the tree, if any
+            while let Poll::Ready(Some(state)) = self.state.poll_next_unpin(cx) {
+                self.on_state_change(state);
+            }
+
+            // advance the checkpoint sync bootstrap, if one is configured and still pending; only
+            // while the tree is active, since a failed fetch falls back to live-downloading the
+            // deferred ranges below, and doing that while offline/syncing would violate the pause
+            // invariant `on_state_change` otherwise enforces
+            if self.last_state.is_active() {
+                if let Some(fetcher) = self.checkpoint.as_mut() {
+                    if let Poll::Ready(anchor) = fetcher.poll_fetch(cx) {
+                        self.checkpoint = None;
+                        self.bootstrapped = true;
+                        if anchor.is_some() {
+                            // the anchor landed, so every range we deferred while bootstrapping
+                            // was, by construction, below it: that span is backfill's job, not
+                            // live sync's, so drop them here rather than re-requesting them as
+                            // live downloads. `Bootstrap` below is what drives the tree to kick
+                            // off `BackfillAction::Backfill` up to the anchor.
+                            self.deferred_block_ranges.clear();
+                        } else {
+                            // the checkpoint fetch failed permanently: there's no anchor for
+                            // backfill to target, so fall back to live-downloading the deferred
+                            // ranges.
+                            let deferred =
+                                self.deferred_block_ranges.drain(..).collect::<Vec<_>>();
+                            for req in deferred {
+                                self.dispatch_or_queue_block_range(req);
+                            }
+                        }
+                        if let Some((block, state)) = anchor {
+                            self.handler.on_event(FromEngine::Bootstrap { block, state });
+                        }
+                    }
+                }
+            }
+
             // drain the handler first
             while let Poll::Ready(ev) = self.handler.poll(cx) {
                 match ev {
@@ -79,6 +198,8 @@ where
                             HandlerEvent::BackfillAction(target) => {
                                 // bubble up backfill sync request request
                                 self.downloader.on_action(DownloadAction::Clear);
+                                self.in_flight_downloads.clear();
+                                self.queued_block_ranges.clear();
                                 Poll::Ready(HandlerEvent::BackfillAction(target))
                             }
                             HandlerEvent::Event(ev) => {
@@ -89,24 +210,68 @@ where
                         }
                     }
                     RequestHandlerEvent::Download(req) => {
-                        // delegate download request to the downloader
-                        self.downloader.on_action(DownloadAction::Download(req));
+                        if !self.bootstrapped && matches!(req, DownloadRequest::BlockRange(..)) {
+                            // the checkpoint fetch is still pending: every range is deferred
+                            // (there is no height check here) instead of racing backfill with a
+                            // live range download
+                            self.deferred_block_ranges.push(req);
+                        } else if !self.last_state.is_active() {
+                            // the tree is paused: hold the download until `on_state_change`
+                            // requests it once the tree is back online
+                            self.paused_downloads.push(InFlightDownload::new(req));
+                        } else if matches!(req, DownloadRequest::BlockRange(..)) {
+                            self.dispatch_or_queue_block_range(req);
+                        } else {
+                            // delegate download request to the downloader
+                            self.in_flight_downloads.push(InFlightDownload::new(req.clone()));
+                            self.downloader.on_action(DownloadAction::Download(req));
+                        }
                     }
                 }
             }
 
-            // pop the next incoming request
-            if let Poll::Ready(Some(req)) = self.incoming_requests.poll_next_unpin(cx) {
-                // and delegate the request to the handler
+            // the tree can't consume any more work right now: stop pulling new requests and
+            // downloads, and just wait for the next state transition.
+            if !self.last_state.is_active() {
+                return Poll::Pending
+            }
+
+            // buffer all currently available incoming requests into the scheduler; it enforces
+            // the per-priority queue caps and coalesces redundant forkchoice updates
+            while let Poll::Ready(Some(req)) = self.incoming_requests.poll_next_unpin(cx) {
+                self.scheduler.push(req);
+            }
+
+            // hand the highest priority scheduled request to the handler, if any is ready;
+            // forkchoice updates are always drained ahead of queued payloads. At most one request
+            // is popped per iteration and the downloader is still polled below in the same
+            // iteration, so a sustained burst of scheduled requests can't starve downloads.
+            let scheduled = self.scheduler.pop();
+            if let Some(req) = scheduled {
                 self.handler.on_event(FromEngine::Request(req));
-                // skip downloading in this iteration to allow the handler to process the request
-                continue
             }
 
             // advance the downloader
-            if let Poll::Ready(DownloadOutcome::Blocks(blocks)) = self.downloader.poll(cx) {
-                // delegate the downloaded blocks to the handler
-                self.handler.on_event(FromEngine::DownloadedBlocks(blocks));
+            match self.downloader.poll(cx) {
+                Poll::Ready(DownloadOutcome::Blocks(blocks)) => {
+                    for block in blocks {
+                        self.queue_downloaded_block(block);
+                    }
+                    continue
+                }
+                Poll::Ready(DownloadOutcome::Blobs(sidecars)) => {
+                    for (versioned_hash, sidecar) in sidecars {
+                        self.insert_downloaded_blob(versioned_hash, sidecar);
+                    }
+                    continue
+                }
+                Poll::Pending => {}
+            }
+
+            if scheduled.is_some() {
+                // we made progress on the scheduler this iteration even though the downloader had
+                // nothing ready; loop back around to keep draining it rather than returning
+                // Pending and waiting on an external wake
                 continue
             }
 
@@ -115,6 +280,398 @@ where
     }
 }
 
+impl<T, S, D> EngineHandler<T, S, D>
+where
+    T: EngineRequestHandler,
+    D: BlockDownloader,
+{
+    /// Handles a transition of the tree's [`EngineState`].
+    ///
+    /// When the tree goes offline or starts backfilling, in-flight downloads are cleared so
+    /// bandwidth isn't wasted buffering blocks the tree can't consume yet. When it comes back
+    /// online, any downloads that were cleared, or requested while it was offline, are requested;
+    /// `BlockRange`s go through [`Self::dispatch_or_queue_block_range`] so at most one is in
+    /// flight.
+    fn on_state_change(&mut self, state: EngineState) {
+        if state == self.last_state {
+            return
+        }
+
+        if state.is_active() {
+            for req in std::mem::take(&mut self.paused_downloads) {
+                if matches!(req.request, DownloadRequest::BlockRange(..)) {
+                    self.dispatch_or_queue_block_range(req.request);
+                } else {
+                    self.downloader.on_action(DownloadAction::Download(req.request.clone()));
+                    self.in_flight_downloads.push(req);
+                }
+            }
+        } else {
+            self.downloader.on_action(DownloadAction::Clear);
+            self.paused_downloads.append(&mut self.in_flight_downloads);
+        }
+
+        self.last_state = state;
+    }
+
+    /// Handles a block that just finished downloading.
+    ///
+    /// If the block doesn't carry any blob versioned hashes it is forwarded to the handler
+    /// immediately. Otherwise the block is buffered and a [`DownloadRequest::BlobSet`] is issued
+    /// for its sidecars; the block is only forwarded once all of its blobs have arrived.
+    fn queue_downloaded_block(&mut self, block: SealedBlockWithSenders) {
+        self.complete_in_flight_block(block.hash());
+
+        let versioned_hashes = block.blob_versioned_hashes();
+        if versioned_hashes.is_empty() {
+            self.handler.on_event(FromEngine::DownloadedBlocks(vec![block]));
+            return
+        }
+
+        let missing = versioned_hashes.into_iter().collect::<HashSet<_>>();
+        let blob_request = DownloadRequest::BlobSet(missing.iter().copied().collect());
+        self.in_flight_downloads.push(InFlightDownload::new(blob_request.clone()));
+        self.downloader.on_action(DownloadAction::Download(blob_request));
+        self.pending_blobs
+            .insert(block.hash(), PendingBlobBlock { block, missing, sidecars: Vec::new() });
+    }
+
+    /// Records a downloaded blob sidecar and forwards its block once all of the block's sidecars
+    /// have arrived.
+    fn insert_downloaded_blob(&mut self, versioned_hash: B256, sidecar: BlobTransactionSidecar) {
+        self.complete_in_flight_blob(versioned_hash);
+
+        let Some(hash) = self
+            .pending_blobs
+            .iter()
+            .find(|(_, pending)| pending.missing.contains(&versioned_hash))
+            .map(|(hash, _)| *hash)
+        else {
+            return
+        };
+
+        let pending = self.pending_blobs.get_mut(&hash).expect("checked above");
+        pending.missing.remove(&versioned_hash);
+        pending.sidecars.push(sidecar);
+
+        if pending.missing.is_empty() {
+            let pending = self.pending_blobs.remove(&hash).expect("checked above");
+            self.handler.on_event(FromEngine::DownloadedBlocks(vec![pending.block]));
+            self.handler.on_event(FromEngine::DownloadedBlobs(hash, pending.sidecars));
+        }
+    }
+
+    /// Marks one downloaded block as complete against whichever in-flight request produced it,
+    /// removing that request once it has no more blocks outstanding.
+    ///
+    /// A `BlockSet` that lists the block's hash is matched first. Otherwise the block is
+    /// attributed to the in-flight `BlockRange`: at most one is ever in flight (see
+    /// [`EngineHandler::queued_block_ranges`]), so there's never more than one candidate.
+    fn complete_in_flight_block(&mut self, hash: B256) {
+        let idx = self
+            .in_flight_downloads
+            .iter()
+            .position(|req| {
+                matches!(&req.request, DownloadRequest::BlockSet(hashes) if hashes.contains(&hash))
+            })
+            .or_else(|| {
+                self.in_flight_downloads
+                    .iter()
+                    .position(|req| matches!(req.request, DownloadRequest::BlockRange(..)))
+            });
+        let Some(idx) = idx else { return };
+        let is_range =
+            matches!(self.in_flight_downloads[idx].request, DownloadRequest::BlockRange(..));
+        if !self.in_flight_downloads[idx].complete_block(hash) {
+            self.in_flight_downloads.remove(idx);
+            if is_range {
+                if let Some(next) = self.queued_block_ranges.pop_front() {
+                    self.dispatch_or_queue_block_range(next);
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if a `BlockRange` download is currently in flight.
+    fn block_range_in_flight(&self) -> bool {
+        self.in_flight_downloads
+            .iter()
+            .any(|req| matches!(req.request, DownloadRequest::BlockRange(..)))
+    }
+
+    /// Dispatches a `BlockRange` download immediately if none is in flight yet; otherwise queues
+    /// it so at most one `BlockRange` is ever in flight at a time.
+    fn dispatch_or_queue_block_range(&mut self, req: DownloadRequest) {
+        debug_assert!(matches!(req, DownloadRequest::BlockRange(..)));
+        if self.block_range_in_flight() {
+            self.queued_block_ranges.push_back(req);
+        } else {
+            self.in_flight_downloads.push(InFlightDownload::new(req.clone()));
+            self.downloader.on_action(DownloadAction::Download(req));
+        }
+    }
+
+    /// Marks one downloaded blob sidecar as complete against whichever in-flight request produced
+    /// it, removing that request once it has no more blobs outstanding.
+    fn complete_in_flight_blob(&mut self, versioned_hash: B256) {
+        let idx = self.in_flight_downloads.iter().position(|req| match &req.request {
+            DownloadRequest::BlobSet(hashes) => hashes.contains(&versioned_hash),
+            _ => false,
+        });
+        let Some(idx) = idx else { return };
+        if !self.in_flight_downloads[idx].complete_blob(versioned_hash) {
+            self.in_flight_downloads.remove(idx);
+        }
+    }
+}
+
+/// A block that finished downloading but is still waiting on one or more blob sidecars.
+#[derive(Debug)]
+struct PendingBlobBlock {
+    /// The downloaded block.
+    block: SealedBlockWithSenders,
+    /// The blob versioned hashes that are still outstanding.
+    missing: HashSet<B256>,
+    /// Sidecars that have arrived so far.
+    sidecars: Vec<BlobTransactionSidecar>,
+}
+
+/// A [`DownloadRequest`] that's currently outstanding, together with how many more items it's
+/// still waiting on so [`EngineHandler`] can drop it once fully delivered instead of only on a
+/// full [`DownloadAction::Clear`].
+#[derive(Debug, Clone)]
+struct InFlightDownload {
+    request: DownloadRequest,
+    /// Remaining items expected for range-style requests. `None` for set-style requests, whose
+    /// own `HashSet` is the source of truth for what's left.
+    remaining: Option<usize>,
+}
+
+impl InFlightDownload {
+    /// Wraps a freshly issued [`DownloadRequest`], computing how many items it's waiting on.
+    fn new(request: DownloadRequest) -> Self {
+        let remaining = match &request {
+            DownloadRequest::BlockRange(_, count) => Some(*count as usize),
+            DownloadRequest::BlockSet(_) | DownloadRequest::BlobSet(_) => None,
+        };
+        Self { request, remaining }
+    }
+
+    /// Accounts for one completed block against this request. Returns `true` if the request still
+    /// has blocks outstanding.
+    fn complete_block(&mut self, hash: B256) -> bool {
+        match &mut self.request {
+            DownloadRequest::BlockSet(hashes) => {
+                hashes.remove(&hash);
+                !hashes.is_empty()
+            }
+            DownloadRequest::BlockRange(..) => {
+                self.remaining = self.remaining.map(|remaining| remaining.saturating_sub(1));
+                self.remaining != Some(0)
+            }
+            _ => true,
+        }
+    }
+
+    /// Accounts for one completed blob sidecar against this request. Returns `true` if the
+    /// request still has blobs outstanding.
+    fn complete_blob(&mut self, versioned_hash: B256) -> bool {
+        match &mut self.request {
+            DownloadRequest::BlobSet(hashes) => {
+                hashes.remove(&versioned_hash);
+                !hashes.is_empty()
+            }
+            _ => true,
+        }
+    }
+}
+
+/// The state of the tree executor, as observed by the [`EngineHandler`].
+///
+/// The tree notifies its handler of transitions between these states via
+/// [`EngineRequestHandler::state_changes`], so the handler can stop feeding it work it has no
+/// capacity to process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EngineState {
+    /// The tree is caught up and ready to process new payloads and downloads.
+    #[default]
+    Online,
+    /// The tree executor is unavailable, e.g. shut down or the database is locked.
+    Offline,
+    /// The tree is backfilling and can't accept live-sync work.
+    Syncing,
+}
+
+impl EngineState {
+    /// Returns `true` if the tree can accept new requests and downloads.
+    pub const fn is_active(&self) -> bool {
+        matches!(self, Self::Online)
+    }
+}
+
+/// Configuration for checkpoint (weak-subjectivity) sync: seed the tree with a trusted finalized
+/// block instead of replaying the full history from genesis.
+#[derive(Debug, Clone)]
+pub struct CheckpointSyncConfig {
+    /// URL of the trusted source to fetch the anchor block and its state from.
+    pub trusted_source: String,
+    /// Hash of the trusted finalized block to bootstrap from.
+    pub target: B256,
+}
+
+/// Fetches the anchor block (and its state) described by a [`CheckpointSyncConfig`].
+///
+/// [`EngineHandler`] polls this from its `poll` loop, while the tree is online, until it resolves,
+/// and emits [`FromEngine::Bootstrap`] the moment it resolves with an anchor; everything below the anchor is then expected to be filled in by
+/// [`BackfillAction::Backfill`] rather than live `BlockRange` downloads.
+pub trait CheckpointFetcher: std::fmt::Debug + Send + 'static {
+    /// Polls for the anchor block and its state. `Poll::Ready(None)` means the fetch permanently
+    /// failed and won't be retried.
+    fn poll_fetch(&mut self, cx: &mut Context<'_>) -> Poll<Option<(SealedBlockWithSenders, Bytes)>>;
+}
+
+/// The scheduling priority class of a request held by the [`EngineHandler`]'s [`RequestScheduler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RequestPriority {
+    /// `forkchoiceUpdated` requests advance the canonical head and are latency critical; they
+    /// are always drained ahead of lower priority work.
+    ForkchoiceUpdated,
+    /// Everything else, e.g. `newPayload`.
+    Payload,
+}
+
+/// A request whose scheduling priority the [`EngineHandler`] can reason about.
+pub trait PrioritizedRequest {
+    /// Returns the scheduling class of this request.
+    fn priority(&self) -> RequestPriority;
+
+    /// Returns the forkchoice head this request targets, if it is a `forkchoiceUpdated` request.
+    ///
+    /// Used by the [`RequestScheduler`] to coalesce redundant updates to the same head.
+    fn forkchoice_head(&self) -> Option<B256> {
+        None
+    }
+}
+
+impl<T: EngineTypes> PrioritizedRequest for BeaconEngineMessage<T> {
+    fn priority(&self) -> RequestPriority {
+        match self {
+            Self::ForkchoiceUpdated { .. } => RequestPriority::ForkchoiceUpdated,
+            _ => RequestPriority::Payload,
+        }
+    }
+
+    fn forkchoice_head(&self) -> Option<B256> {
+        match self {
+            Self::ForkchoiceUpdated { state, .. } => Some(state.head_block_hash),
+            _ => None,
+        }
+    }
+}
+
+/// Bounded, prioritized scheduler for incoming engine requests.
+///
+/// Maintains a separate FIFO queue per [`RequestPriority`], each capped at `max_queue_len`.
+/// [`RequestScheduler::pop`] always drains the `ForkchoiceUpdated` queue before the `Payload`
+/// queue. Pushing a forkchoice update that targets the same head as one already queued replaces
+/// the older entry rather than queueing a duplicate. When a queue is at capacity, the oldest entry
+/// is dropped to make room for the newest, favoring freshness over completeness for backlogged
+/// low-priority work.
+#[derive(Debug)]
+struct RequestScheduler<Req> {
+    forkchoice: VecDeque<Req>,
+    payloads: VecDeque<Req>,
+    max_queue_len: usize,
+}
+
+impl<Req: PrioritizedRequest> RequestScheduler<Req> {
+    /// Creates an empty scheduler with the given per-priority queue cap.
+    const fn new(max_queue_len: usize) -> Self {
+        Self { forkchoice: VecDeque::new(), payloads: VecDeque::new(), max_queue_len }
+    }
+
+    /// Buffers a request, coalescing redundant forkchoice updates and enforcing the queue cap.
+    fn push(&mut self, req: Req) {
+        if let Some(head) = req.forkchoice_head() {
+            self.forkchoice.retain(|queued| queued.forkchoice_head() != Some(head));
+            self.forkchoice.push_back(req);
+            if self.forkchoice.len() > self.max_queue_len {
+                self.forkchoice.pop_front();
+            }
+            return
+        }
+
+        if self.payloads.len() >= self.max_queue_len {
+            self.payloads.pop_front();
+        }
+        self.payloads.push_back(req);
+    }
+
+    /// Returns the next request to hand to the handler, forkchoice updates first.
+    fn pop(&mut self) -> Option<Req> {
+        self.forkchoice.pop_front().or_else(|| self.payloads.pop_front())
+    }
+}
+
+/// A request for payload bodies, e.g. `engine_getPayloadBodiesByRangeV1` /
+/// `engine_getPayloadBodiesByHashV1`.
+///
+/// Unlike [`BeaconEngineMessage`], these are pure reads and don't need to go through the tree's
+/// exclusive-access request queue, so the [`EngineApiRequestHandler`] answers them directly from
+/// its [`PayloadBodiesProvider`] and they can be processed concurrently with payload execution.
+#[derive(Debug)]
+pub enum PayloadBodiesRequest {
+    /// `engine_getPayloadBodiesByRangeV1`: returns the bodies of `count` blocks starting at
+    /// height `start`.
+    ByRange(u64, u64, oneshot::Sender<Vec<Option<ExecutionPayloadBodyV1>>>),
+    /// `engine_getPayloadBodiesByHashV1`: returns the bodies of the given block hashes, in order.
+    ByHash(Vec<B256>, oneshot::Sender<Vec<Option<ExecutionPayloadBodyV1>>>),
+}
+
+/// A read-only source of payload bodies backing [`PayloadBodiesRequest`].
+///
+/// Implementations resolve bodies (transactions + withdrawals) from the tree/provider. Missing or
+/// pre-merge blocks map to `None` rather than shortening the returned `Vec`.
+pub trait PayloadBodiesProvider: Send + Sync + 'static {
+    /// Returns the payload bodies for `count` blocks starting at height `start`, in order.
+    fn payload_bodies_by_range(
+        &self,
+        start: u64,
+        count: u64,
+    ) -> Vec<Option<ExecutionPayloadBodyV1>>;
+
+    /// Returns the payload bodies for the given block hashes, in order.
+    fn payload_bodies_by_hash(&self, hashes: Vec<B256>) -> Vec<Option<ExecutionPayloadBodyV1>>;
+}
+
+/// The request type processed by [`EngineApiRequestHandler`].
+///
+/// Splits requests that must be serialized through the tree executor (`Beacon`) from read-only
+/// [`PayloadBodiesRequest`]s that can be answered off the critical path.
+#[derive(Debug)]
+pub enum EngineApiRequest<T: EngineTypes> {
+    /// A beacon consensus engine request (`newPayload`, `forkchoiceUpdated`, ...).
+    Beacon(BeaconEngineMessage<T>),
+    /// A request for payload bodies.
+    PayloadBodies(PayloadBodiesRequest),
+}
+
+impl<T: EngineTypes> PrioritizedRequest for EngineApiRequest<T> {
+    fn priority(&self) -> RequestPriority {
+        match self {
+            Self::Beacon(msg) => msg.priority(),
+            Self::PayloadBodies(_) => RequestPriority::Payload,
+        }
+    }
+
+    fn forkchoice_head(&self) -> Option<B256> {
+        match self {
+            Self::Beacon(msg) => msg.forkchoice_head(),
+            Self::PayloadBodies(_) => None,
+        }
+    }
+}
+
 /// A type that processes incoming requests (e.g. requests from the consensus layer, engine API,
 /// such as newPayload).
 ///
@@ -134,6 +691,10 @@ pub trait EngineRequestHandler: Send + Sync {
 
     /// Advances the handler.
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<RequestHandlerEvent<Self::Event>>;
+
+    /// Returns a receiver that is notified of [`EngineState`] transitions of the underlying tree
+    /// executor.
+    fn state_changes(&self) -> watch::Receiver<EngineState>;
 }
 
 /// An [`EngineRequestHandler`] that processes engine API requests by delegating to an execution
@@ -155,35 +716,81 @@ pub trait EngineRequestHandler: Send + Sync {
 ///
 /// In case required blocks are missing, the handler will request them from the network, by emitting
 /// a download request upstream.
+///
+/// [`PayloadBodiesRequest`]s (`engine_getPayloadBodies{ByRange,ByHash}V1`) are a third, read-only
+/// category: they're answered directly from the `provider` without round-tripping through the
+/// tree, so they don't compete with `newPayload`/`forkchoiceUpdated` for exclusive DB access.
 #[derive(Debug)]
-pub struct EngineApiRequestHandler<T: EngineTypes> {
+pub struct EngineApiRequestHandler<T: EngineTypes, P> {
     /// channel to send messages to the tree to execute the payload.
     to_tree: Sender<FromEngine<BeaconEngineMessage<T>>>,
     /// channel to receive messages from the tree.
     from_tree: UnboundedReceiver<EngineApiEvent>,
+    /// notifies about [`EngineState`] transitions of the tree.
+    state: watch::Receiver<EngineState>,
+    /// serves [`PayloadBodiesRequest`]s without going through the tree. Wrapped in an `Arc` so a
+    /// request can be answered from a [`tokio::task::spawn_blocking`] task instead of inline on
+    /// this handler's `poll` loop.
+    provider: Arc<P>,
 }
 
-impl<T> EngineApiRequestHandler<T>
+impl<T, P> EngineApiRequestHandler<T, P>
 where
     T: EngineTypes,
 {
     /// Creates a new `EngineApiRequestHandler`.
-    pub const fn new(
+    pub fn new(
         to_tree: Sender<FromEngine<BeaconEngineMessage<T>>>,
         from_tree: UnboundedReceiver<EngineApiEvent>,
+        state: watch::Receiver<EngineState>,
+        provider: P,
     ) -> Self {
-        Self { to_tree, from_tree }
+        Self { to_tree, from_tree, state, provider: Arc::new(provider) }
     }
 }
 
-impl<T> EngineRequestHandler for EngineApiRequestHandler<T>
+impl<T, P> EngineApiRequestHandler<T, P>
 where
     T: EngineTypes,
+    P: PayloadBodiesProvider,
+{
+    /// Answers a [`PayloadBodiesRequest`] from the provider off the engine task, via
+    /// [`tokio::task::spawn_blocking`], so a large range query can't stall `forkchoiceUpdated`
+    /// processing on this handler's `poll` loop.
+    fn on_payload_bodies_request(&self, request: PayloadBodiesRequest) {
+        let provider = self.provider.clone();
+        tokio::task::spawn_blocking(move || match request {
+            PayloadBodiesRequest::ByRange(start, count, tx) => {
+                let _ = tx.send(provider.payload_bodies_by_range(start, count));
+            }
+            PayloadBodiesRequest::ByHash(hashes, tx) => {
+                let _ = tx.send(provider.payload_bodies_by_hash(hashes));
+            }
+        });
+    }
+}
+
+impl<T, P> EngineRequestHandler for EngineApiRequestHandler<T, P>
+where
+    T: EngineTypes,
+    P: PayloadBodiesProvider,
 {
     type Event = BeaconConsensusEngineEvent;
-    type Request = BeaconEngineMessage<T>;
+    type Request = EngineApiRequest<T>;
 
     fn on_event(&mut self, event: FromEngine<Self::Request>) {
+        let event = match event {
+            FromEngine::Request(EngineApiRequest::PayloadBodies(request)) => {
+                return self.on_payload_bodies_request(request)
+            }
+            FromEngine::Request(EngineApiRequest::Beacon(msg)) => FromEngine::Request(msg),
+            FromEngine::Event(ev) => FromEngine::Event(ev),
+            FromEngine::DownloadedBlocks(blocks) => FromEngine::DownloadedBlocks(blocks),
+            FromEngine::DownloadedBlobs(hash, sidecars) => {
+                FromEngine::DownloadedBlobs(hash, sidecars)
+            }
+            FromEngine::Bootstrap { block, state } => FromEngine::Bootstrap { block, state },
+        };
         // delegate to the tree
         let _ = self.to_tree.send(event);
     }
@@ -204,6 +811,10 @@ where
         };
         Poll::Ready(ev)
     }
+
+    fn state_changes(&self) -> watch::Receiver<EngineState> {
+        self.state.clone()
+    }
 }
 
 /// The type for specify which is kind of engine api
@@ -249,6 +860,16 @@ pub enum FromEngine<Req> {
     Request(Req),
     /// Downloaded blocks from the network.
     DownloadedBlocks(Vec<SealedBlockWithSenders>),
+    /// Downloaded blob sidecars for a block from the network.
+    DownloadedBlobs(B256, Vec<BlobTransactionSidecar>),
+    /// Seeds the tree with a trusted, finalized block (and its state) to sync from, as part of
+    /// checkpoint (weak-subjectivity) sync.
+    Bootstrap {
+        /// The trusted finalized anchor block.
+        block: SealedBlockWithSenders,
+        /// The anchor block's state, as fetched from the trusted source.
+        state: Bytes,
+    },
 }
 
 impl<Req> From<FromOrchestrator> for FromEngine<Req> {
@@ -267,12 +888,19 @@ pub enum RequestHandlerEvent<T> {
 }
 
 /// A request to download blocks from the network.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum DownloadRequest {
     /// Download the given set of blocks.
     BlockSet(HashSet<B256>),
     /// Download the given range of blocks.
     BlockRange(B256, u64),
+    /// Download the blob sidecars for the given set of versioned hashes.
+    ///
+    /// This is the only way blob sidecars are fetched: on demand, for the versioned hashes of a
+    /// single already-downloaded block. There is no range-based blob request (no
+    /// `BlobsByRange`/`BlobsByRoot`-style network request), and blob sidecars are not tracked in
+    /// batches during range sync.
+    BlobSet(HashSet<B256>),
 }
 
 impl DownloadRequest {
@@ -281,3 +909,234 @@ impl DownloadRequest {
         Self::BlockSet(HashSet::from([hash]))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal [`PrioritizedRequest`] for exercising [`RequestScheduler`] in isolation, without
+    /// pulling in [`BeaconEngineMessage`]'s `EngineTypes` bound.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum TestRequest {
+        Forkchoice(B256),
+        Payload(u64),
+    }
+
+    impl PrioritizedRequest for TestRequest {
+        fn priority(&self) -> RequestPriority {
+            match self {
+                Self::Forkchoice(_) => RequestPriority::ForkchoiceUpdated,
+                Self::Payload(_) => RequestPriority::Payload,
+            }
+        }
+
+        fn forkchoice_head(&self) -> Option<B256> {
+            match self {
+                Self::Forkchoice(head) => Some(*head),
+                Self::Payload(_) => None,
+            }
+        }
+    }
+
+    #[test]
+    fn pop_drains_forkchoice_before_payloads() {
+        let mut scheduler = RequestScheduler::new(10);
+        scheduler.push(TestRequest::Payload(1));
+        scheduler.push(TestRequest::Forkchoice(B256::with_last_byte(1)));
+        scheduler.push(TestRequest::Payload(2));
+
+        assert_eq!(scheduler.pop(), Some(TestRequest::Forkchoice(B256::with_last_byte(1))));
+        assert_eq!(scheduler.pop(), Some(TestRequest::Payload(1)));
+        assert_eq!(scheduler.pop(), Some(TestRequest::Payload(2)));
+        assert_eq!(scheduler.pop(), None);
+    }
+
+    #[test]
+    fn push_coalesces_forkchoice_updates_to_the_same_head() {
+        let mut scheduler = RequestScheduler::new(10);
+        let head = B256::with_last_byte(1);
+        scheduler.push(TestRequest::Forkchoice(head));
+        scheduler.push(TestRequest::Payload(1));
+        scheduler.push(TestRequest::Forkchoice(head));
+
+        // the second forkchoice update to the same head replaces the first, so only one is
+        // queued, and it moves to the back of the forkchoice queue (not deduplicated away)
+        assert_eq!(scheduler.pop(), Some(TestRequest::Forkchoice(head)));
+        assert_eq!(scheduler.pop(), Some(TestRequest::Payload(1)));
+        assert_eq!(scheduler.pop(), None);
+    }
+
+    #[test]
+    fn push_does_not_coalesce_forkchoice_updates_to_different_heads() {
+        let mut scheduler = RequestScheduler::new(10);
+        let first = B256::with_last_byte(1);
+        let second = B256::with_last_byte(2);
+        scheduler.push(TestRequest::Forkchoice(first));
+        scheduler.push(TestRequest::Forkchoice(second));
+
+        assert_eq!(scheduler.pop(), Some(TestRequest::Forkchoice(first)));
+        assert_eq!(scheduler.pop(), Some(TestRequest::Forkchoice(second)));
+        assert_eq!(scheduler.pop(), None);
+    }
+
+    #[test]
+    fn push_drops_oldest_payload_once_queue_is_full() {
+        let mut scheduler = RequestScheduler::new(2);
+        scheduler.push(TestRequest::Payload(1));
+        scheduler.push(TestRequest::Payload(2));
+        scheduler.push(TestRequest::Payload(3));
+
+        assert_eq!(scheduler.pop(), Some(TestRequest::Payload(2)));
+        assert_eq!(scheduler.pop(), Some(TestRequest::Payload(3)));
+        assert_eq!(scheduler.pop(), None);
+    }
+
+    #[test]
+    fn push_drops_oldest_forkchoice_update_once_queue_is_full() {
+        let mut scheduler = RequestScheduler::new(2);
+        scheduler.push(TestRequest::Forkchoice(B256::with_last_byte(1)));
+        scheduler.push(TestRequest::Forkchoice(B256::with_last_byte(2)));
+        scheduler.push(TestRequest::Forkchoice(B256::with_last_byte(3)));
+
+        assert_eq!(scheduler.pop(), Some(TestRequest::Forkchoice(B256::with_last_byte(2))));
+        assert_eq!(scheduler.pop(), Some(TestRequest::Forkchoice(B256::with_last_byte(3))));
+        assert_eq!(scheduler.pop(), None);
+    }
+
+    /// A [`BlockDownloader`] test double that records every [`DownloadAction`] issued to it
+    /// instead of actually reaching out to the network.
+    #[derive(Debug, Default)]
+    struct TestDownloader {
+        actions: Vec<DownloadAction>,
+    }
+
+    impl BlockDownloader for TestDownloader {
+        fn on_action(&mut self, action: DownloadAction) {
+            self.actions.push(action);
+        }
+
+        fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<DownloadOutcome> {
+            Poll::Pending
+        }
+    }
+
+    /// Returns the start hash of every `BlockRange` download issued to `downloader`, in issue
+    /// order.
+    fn downloaded_range_starts(downloader: &TestDownloader) -> Vec<B256> {
+        downloader
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                DownloadAction::Download(DownloadRequest::BlockRange(start, _)) => Some(*start),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// A minimal [`EngineRequestHandler`] test double; [`EngineHandler`]'s pause/resume and
+    /// single-flight bookkeeping don't depend on anything it does, so it's just enough to satisfy
+    /// the generic bound.
+    #[derive(Debug)]
+    struct TestHandler {
+        state_rx: watch::Receiver<EngineState>,
+    }
+
+    impl TestHandler {
+        fn new() -> Self {
+            let (_tx, state_rx) = watch::channel(EngineState::Online);
+            Self { state_rx }
+        }
+    }
+
+    impl EngineRequestHandler for TestHandler {
+        type Event = ();
+        type Request = TestRequest;
+
+        fn on_event(&mut self, _event: FromEngine<Self::Request>) {}
+
+        fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<RequestHandlerEvent<Self::Event>> {
+            Poll::Pending
+        }
+
+        fn state_changes(&self) -> watch::Receiver<EngineState> {
+            self.state_rx.clone()
+        }
+    }
+
+    /// An [`EngineHandler`] wired up with test doubles, for exercising its download bookkeeping
+    /// directly without a real handler or downloader.
+    fn test_engine_handler(
+    ) -> EngineHandler<TestHandler, futures::stream::Empty<TestRequest>, TestDownloader> {
+        EngineHandler::new(TestHandler::new(), TestDownloader::default(), futures::stream::empty())
+    }
+
+    #[test]
+    fn concurrent_block_ranges_complete_independently_in_order() {
+        let mut handler = test_engine_handler();
+        let range_a = DownloadRequest::BlockRange(B256::with_last_byte(1), 2);
+        let range_b = DownloadRequest::BlockRange(B256::with_last_byte(2), 2);
+
+        // at most one `BlockRange` is ever in flight: the second is queued behind the first
+        // instead of running alongside it.
+        handler.dispatch_or_queue_block_range(range_a.clone());
+        handler.dispatch_or_queue_block_range(range_b.clone());
+
+        assert_eq!(handler.in_flight_downloads.len(), 1);
+        assert_eq!(handler.queued_block_ranges.len(), 1);
+        assert_eq!(downloaded_range_starts(&handler.downloader), vec![B256::with_last_byte(1)]);
+
+        // the first of range_a's two blocks lands: range_a must stay in flight and range_b must
+        // stay queued, since it's not done yet.
+        handler.complete_in_flight_block(B256::with_last_byte(10));
+        assert_eq!(handler.in_flight_downloads.len(), 1);
+        assert_eq!(handler.queued_block_ranges.len(), 1);
+        assert_eq!(downloaded_range_starts(&handler.downloader), vec![B256::with_last_byte(1)]);
+
+        // range_a's second and final block lands: range_a is evicted and range_b is dispatched
+        // immediately after, rather than a block that actually belongs to range_b ever being
+        // attributed to range_a's remaining counter.
+        handler.complete_in_flight_block(B256::with_last_byte(11));
+        assert!(handler.queued_block_ranges.is_empty());
+        assert_eq!(
+            downloaded_range_starts(&handler.downloader),
+            vec![B256::with_last_byte(1), B256::with_last_byte(2)]
+        );
+
+        // range_b's two blocks land and it's evicted too, with nothing left queued behind it.
+        handler.complete_in_flight_block(B256::with_last_byte(20));
+        handler.complete_in_flight_block(B256::with_last_byte(21));
+        assert!(handler.in_flight_downloads.is_empty());
+    }
+
+    #[test]
+    fn offline_cycle_replays_in_flight_blob_download() {
+        let mut handler = test_engine_handler();
+
+        // a block finished downloading but is waiting on a blob sidecar, the same way
+        // `queue_downloaded_block` tracks it.
+        let blob_request = DownloadRequest::BlobSet(HashSet::from([B256::with_last_byte(1)]));
+        handler.in_flight_downloads.push(InFlightDownload::new(blob_request.clone()));
+        handler.downloader.on_action(DownloadAction::Download(blob_request));
+
+        // the tree goes offline: the outstanding blob download moves to `paused_downloads`
+        // instead of being silently dropped.
+        handler.on_state_change(EngineState::Offline);
+        assert!(handler.in_flight_downloads.is_empty());
+        assert_eq!(handler.paused_downloads.len(), 1);
+
+        // the tree comes back online: the paused blob download is re-issued to the downloader and
+        // re-tracked as in flight, so the block waiting on it is never stranded.
+        handler.on_state_change(EngineState::Online);
+        assert!(handler.paused_downloads.is_empty());
+        assert_eq!(handler.in_flight_downloads.len(), 1);
+        assert_eq!(
+            handler
+                .downloader
+                .actions
+                .iter()
+                .filter(|action| matches!(action, DownloadAction::Download(DownloadRequest::BlobSet(..))))
+                .count(),
+            2,
+        );
+    }
+}